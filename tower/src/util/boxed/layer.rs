@@ -1,5 +1,5 @@
-use crate::util::BoxService;
-use std::{fmt, sync::Arc};
+use crate::util::{BoxCloneService, BoxService, UnsyncBoxService};
+use std::{fmt, rc::Rc, sync::Arc};
 use tower_layer::{layer_fn, Layer};
 use tower_service::Service;
 
@@ -11,6 +11,11 @@ use tower_service::Service;
 /// This [`Layer`] produces [`BoxService`] instances erasing the type of the
 /// [`Service`] produced by the wrapped [`Layer`].
 ///
+/// Since the `layer_fn` closure `BoxLayer` builds hands the wrapped [`Layer`]'s un-erased
+/// output straight to [`BoxService::new`], the resulting [`BoxService`] can still be
+/// downcast back to it with [`BoxService::downcast_ref`], [`BoxService::downcast_mut`], or
+/// [`BoxService::into_inner`].
+///
 /// # Example
 ///
 /// `BoxLayer` can, for example, be useful to create layers dynamically that otherwise wouldn't have
@@ -24,7 +29,7 @@ use tower_service::Service;
 ///
 /// fn common_layer<'a, S, T>() -> BoxLayer<'a, S, T, S::Response, BoxError>
 /// where
-///     S: Service<T> + Send + 'a,
+///     S: Service<T> + Send + 'static,
 ///     S::Future: Send + 'a,
 ///     S::Error: Into<BoxError> + 'a,
 /// {
@@ -50,6 +55,10 @@ use tower_service::Service;
 /// [`Layer`]: tower_layer::Layer
 /// [`Service`]: tower_service::Service
 /// [`BoxService`]: super::BoxService
+/// [`BoxService::new`]: super::BoxService::new
+/// [`BoxService::downcast_ref`]: super::BoxService::downcast_ref
+/// [`BoxService::downcast_mut`]: super::BoxService::downcast_mut
+/// [`BoxService::into_inner`]: super::BoxService::into_inner
 /// [`Timeout`]: crate::timeout
 pub struct BoxLayer<'a, In, T, U, E> {
     boxed: Arc<dyn Layer<In, Service = BoxService<'a, T, U, E>> + Send + Sync + 'a>,
@@ -60,7 +69,7 @@ impl<'a, In, T, U, E> BoxLayer<'a, In, T, U, E> {
     pub fn new<L>(inner_layer: L) -> Self
     where
         L: Layer<In> + Send + Sync + 'a,
-        L::Service: Service<T, Response = U, Error = E> + Send + 'a,
+        L::Service: Service<T, Response = U, Error = E> + Send + 'static,
         <L::Service as Service<T>>::Future: Send + 'a,
     {
         let layer = layer_fn(move |inner: In| {
@@ -95,3 +104,198 @@ impl<'a, In, T, U, E> fmt::Debug for BoxLayer<'a, In, T, U, E> {
         fmt.debug_struct("BoxLayer").finish()
     }
 }
+
+/// A boxed [`Layer`] trait object, like [`BoxLayer`], whose output [`Service`] is [`Clone`].
+///
+/// [`BoxCloneServiceLayer`] turns a layer into a trait object, allowing both the [`Layer`]
+/// itself and the output [`Service`] to be dynamic, while having consistent types.
+///
+/// This [`Layer`] produces [`BoxCloneService`] instances erasing the type of the
+/// [`Service`] produced by the wrapped [`Layer`]. This is in contrast to [`BoxLayer`],
+/// whose output [`BoxService`] is not [`Clone`]. This type is therefore useful in place
+/// of [`BoxLayer`] whenever the resulting service must be cloned, for example, because
+/// it is shared between multiple connections.
+///
+/// # Example
+///
+/// `BoxCloneServiceLayer` can, for example, be useful to create layers dynamically that
+/// otherwise wouldn't have the same types. In this example, we include a [`Timeout`] layer
+/// only if an environment variable is set. We can use `BoxCloneServiceLayer` to return a
+/// consistent, [`Clone`] type regardless of runtime configuration:
+///
+/// ```
+/// use std::time::Duration;
+/// use tower::{Service, ServiceBuilder, BoxError, util::BoxCloneServiceLayer};
+///
+/// fn common_layer<'a, S, T>() -> BoxCloneServiceLayer<'a, S, T, S::Response, BoxError>
+/// where
+///     S: Service<T> + Clone + Send + 'static,
+///     S::Future: Send + 'static,
+///     S::Error: Into<BoxError> + 'static,
+/// {
+///     let builder = ServiceBuilder::new()
+///         .concurrency_limit(100);
+///
+///     if std::env::var("SET_TIMEOUT").is_ok() {
+///         let layer = builder
+///             .timeout(Duration::from_secs(30))
+///             .into_inner();
+///
+///         BoxCloneServiceLayer::new(layer)
+///     } else {
+///         let layer = builder
+///             .map_err(Into::into)
+///             .into_inner();
+///
+///         BoxCloneServiceLayer::new(layer)
+///     }
+/// }
+/// ```
+///
+/// [`Layer`]: tower_layer::Layer
+/// [`Service`]: tower_service::Service
+/// [`BoxService`]: super::BoxService
+/// [`Timeout`]: crate::timeout
+pub struct BoxCloneServiceLayer<'a, In, T, U, E> {
+    boxed: Arc<dyn Layer<In, Service = BoxCloneService<T, U, E>> + Send + Sync + 'a>,
+}
+
+impl<'a, In, T, U, E> BoxCloneServiceLayer<'a, In, T, U, E> {
+    /// Create a new [`BoxCloneServiceLayer`].
+    pub fn new<L>(inner_layer: L) -> Self
+    where
+        L: Layer<In> + Send + Sync + 'a,
+        L::Service: Service<T, Response = U, Error = E> + Clone + Send + 'static,
+        <L::Service as Service<T>>::Future: Send + 'static,
+    {
+        let layer = layer_fn(move |inner: In| {
+            let out = inner_layer.layer(inner);
+            BoxCloneService::new(out)
+        });
+
+        Self {
+            boxed: Arc::new(layer),
+        }
+    }
+}
+
+impl<'a, In, T, U, E> Layer<In> for BoxCloneServiceLayer<'a, In, T, U, E> {
+    type Service = BoxCloneService<T, U, E>;
+
+    fn layer(&self, inner: In) -> Self::Service {
+        self.boxed.layer(inner)
+    }
+}
+
+impl<'a, In, T, U, E> Clone for BoxCloneServiceLayer<'a, In, T, U, E> {
+    fn clone(&self) -> Self {
+        Self {
+            boxed: Arc::clone(&self.boxed),
+        }
+    }
+}
+
+impl<'a, In, T, U, E> fmt::Debug for BoxCloneServiceLayer<'a, In, T, U, E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("BoxCloneServiceLayer").finish()
+    }
+}
+
+/// A boxed [`Layer`] trait object, like [`BoxLayer`], for `!Send` layers and services.
+///
+/// [`LocalBoxLayer`] turns a layer into a trait object, allowing both the [`Layer`] itself
+/// and the output [`Service`] to be dynamic, while having consistent types.
+///
+/// Unlike [`BoxLayer`], which requires both the wrapped [`Layer`] and the produced
+/// [`Service`]'s future to be [`Send`], [`LocalBoxLayer`] drops the [`Send`] bound entirely.
+/// This makes it suitable for middleware built on `Rc`, `RefCell`, or other thread-local
+/// state running on a single-threaded executor, such as a [`tokio::task::LocalSet`] or a
+/// single-threaded `actix` runtime.
+///
+/// This [`Layer`] produces [`UnsyncBoxService`] instances erasing the type of the
+/// [`Service`] produced by the wrapped [`Layer`].
+///
+/// # Example
+///
+/// `LocalBoxLayer` can, for example, be useful to create layers dynamically that otherwise
+/// wouldn't have the same types, on a single-threaded runtime. In this example, we include a
+/// [`Timeout`] layer only if an environment variable is set. We can use `LocalBoxLayer` to
+/// return a consistent type regardless of runtime configuration:
+///
+/// ```
+/// use std::time::Duration;
+/// use tower::{Service, ServiceBuilder, BoxError, util::LocalBoxLayer};
+///
+/// fn common_layer<'a, S, T>() -> LocalBoxLayer<'a, S, T, S::Response, BoxError>
+/// where
+///     S: Service<T> + 'static,
+///     S::Future: 'static,
+///     S::Error: Into<BoxError> + 'static,
+/// {
+///     let builder = ServiceBuilder::new()
+///         .concurrency_limit(100);
+///
+///     if std::env::var("SET_TIMEOUT").is_ok() {
+///         let layer = builder
+///             .timeout(Duration::from_secs(30))
+///             .into_inner();
+///
+///         LocalBoxLayer::new(layer)
+///     } else {
+///         let layer = builder
+///             .map_err(Into::into)
+///             .into_inner();
+///
+///         LocalBoxLayer::new(layer)
+///     }
+/// }
+/// ```
+///
+/// [`Layer`]: tower_layer::Layer
+/// [`Service`]: tower_service::Service
+/// [`UnsyncBoxService`]: super::UnsyncBoxService
+/// [`Timeout`]: crate::timeout
+pub struct LocalBoxLayer<'a, In, T, U, E> {
+    boxed: Rc<dyn Layer<In, Service = UnsyncBoxService<T, U, E>> + 'a>,
+}
+
+impl<'a, In, T, U, E> LocalBoxLayer<'a, In, T, U, E> {
+    /// Create a new [`LocalBoxLayer`].
+    pub fn new<L>(inner_layer: L) -> Self
+    where
+        L: Layer<In> + 'a,
+        L::Service: Service<T, Response = U, Error = E> + 'static,
+        <L::Service as Service<T>>::Future: 'static,
+    {
+        let layer = layer_fn(move |inner: In| {
+            let out = inner_layer.layer(inner);
+            UnsyncBoxService::new(out)
+        });
+
+        Self {
+            boxed: Rc::new(layer),
+        }
+    }
+}
+
+impl<'a, In, T, U, E> Layer<In> for LocalBoxLayer<'a, In, T, U, E> {
+    type Service = UnsyncBoxService<T, U, E>;
+
+    fn layer(&self, inner: In) -> Self::Service {
+        self.boxed.layer(inner)
+    }
+}
+
+impl<'a, In, T, U, E> Clone for LocalBoxLayer<'a, In, T, U, E> {
+    fn clone(&self) -> Self {
+        Self {
+            boxed: Rc::clone(&self.boxed),
+        }
+    }
+}
+
+impl<'a, In, T, U, E> fmt::Debug for LocalBoxLayer<'a, In, T, U, E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("LocalBoxLayer").finish()
+    }
+}
@@ -0,0 +1,132 @@
+//! Tower middleware that erases the type of a [`Service`] or [`Layer`].
+//!
+//! [`Service`]: tower_service::Service
+//! [`Layer`]: tower_layer::Layer
+
+mod layer;
+
+pub use self::layer::{BoxCloneServiceLayer, BoxLayer, LocalBoxLayer};
+
+use std::{
+    any::Any,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tower_service::Service;
+
+type BoxFuture<'a, T, E> = Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'a>>;
+
+/// A boxed [`Service + Send`] trait object.
+///
+/// [`BoxService`] turns a service into a trait object, allowing the response future type to
+/// be dynamic.
+///
+/// # Downcasting
+///
+/// The wrapped service is kept in a boxed trait object that can also expose it as `dyn Any`,
+/// so the original, concrete service can be recovered after erasure with [`BoxService::downcast_ref`],
+/// [`BoxService::downcast_mut`], or consumed with [`BoxService::into_inner`].
+///
+/// [`Service + Send`]: tower_service::Service
+pub struct BoxService<'a, T, U, E> {
+    inner: Box<dyn ErasedService<'a, T, U, E> + Send + 'a>,
+}
+
+trait ErasedService<'a, T, U, E> {
+    fn erased_poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), E>>;
+    fn erased_call(&mut self, req: T) -> BoxFuture<'a, U, E>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<'a, T, U, E, S> ErasedService<'a, T, U, E> for S
+where
+    S: Service<T, Response = U, Error = E> + Send + 'static,
+    S::Future: Send + 'a,
+{
+    fn erased_poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), E>> {
+        Service::poll_ready(self, cx)
+    }
+
+    fn erased_call(&mut self, req: T) -> BoxFuture<'a, U, E> {
+        Box::pin(Service::call(self, req))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl<'a, T, U, E> BoxService<'a, T, U, E> {
+    /// Create a new [`BoxService`].
+    ///
+    /// The wrapped service must be `'static` so that it can later be recovered through
+    /// [`downcast_ref`], [`downcast_mut`], or [`into_inner`]; only its response future is
+    /// allowed to borrow for `'a`.
+    ///
+    /// [`downcast_ref`]: Self::downcast_ref
+    /// [`downcast_mut`]: Self::downcast_mut
+    /// [`into_inner`]: Self::into_inner
+    pub fn new<S>(inner: S) -> Self
+    where
+        S: Service<T, Response = U, Error = E> + Send + 'static,
+        S::Future: Send + 'a,
+    {
+        Self {
+            inner: Box::new(inner),
+        }
+    }
+
+    /// Returns a reference to the inner service if it is of type `S`, or `None` if it isn't.
+    pub fn downcast_ref<S: 'static>(&self) -> Option<&S> {
+        self.inner.as_any().downcast_ref()
+    }
+
+    /// Returns a mutable reference to the inner service if it is of type `S`, or `None` if it
+    /// isn't.
+    pub fn downcast_mut<S: 'static>(&mut self) -> Option<&mut S> {
+        self.inner.as_any_mut().downcast_mut()
+    }
+
+    /// Consumes the [`BoxService`], returning the inner service if it is of type `S`.
+    ///
+    /// Returns the original [`BoxService`] as `Err` if the inner service isn't of type `S`.
+    pub fn into_inner<S: 'static>(self) -> Result<Box<S>, Self> {
+        if self.inner.as_any().is::<S>() {
+            Ok(self.inner.into_any().downcast().unwrap())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl<'a, T, U, E> Service<T> for BoxService<'a, T, U, E> {
+    type Response = U;
+    type Error = E;
+    type Future = BoxFuture<'a, U, E>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.erased_poll_ready(cx)
+    }
+
+    fn call(&mut self, req: T) -> Self::Future {
+        self.inner.erased_call(req)
+    }
+}
+
+impl<'a, T, U, E> fmt::Debug for BoxService<'a, T, U, E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("BoxService").finish()
+    }
+}